@@ -1,22 +1,44 @@
-use support::{decl_storage, decl_module, decl_event, StorageMap, ensure, Parameter, traits::Currency};
-use sr_primitives::traits::{SimpleArithmetic, Bounded, Member};
+use support::{decl_storage, decl_module, decl_event, StorageMap, StorageValue, ensure, Parameter, traits::{Currency, ReservableCurrency, Get}};
+use sr_primitives::traits::{SimpleArithmetic, Bounded, Member, As, CheckedSub, CheckedMul, CheckedDiv};
 use system::ensure_signed;
 use codec::{Encode, Decode};
 use runtime_io::blake2_128;
+use rstd::prelude::*;
 use rstd::result;
 
-pub trait Trait: system::Trait {
+/// 拍品归属与转移的抽象，供 Kitties/NFT 等其它模块复用本拍卖所为通用子系统
+pub trait ItemTransfer<AccountId, ItemId> {
+    /// 判断 who 是否为该拍品的所有者
+    fn is_item_owner(who: &AccountId, item: ItemId) -> bool;
+    /// 将拍品所有权从 from 转移至 to
+    fn transfer_item(from: &AccountId, to: &AccountId, item: ItemId) -> result::Result<(), &'static str>;
+}
+
+pub trait Trait: system::Trait + timestamp::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type Currency: Currency<Self::AccountId>;
+    type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+    type ItemTransfer: ItemTransfer<Self::AccountId, [u8; 16]>;
     type AuctionIndex: Parameter + Member + SimpleArithmetic + Bounded + Default + Copy;
+    /// 蜡烛拍卖结束期时长（秒）；为0时关闭蜡烛机制
+    type EndingPeriod: Get<u64>;
+    /// 结束期内的子区块采样数量
+    type SampleCount: Get<u64>;
+    /// 拍卖行抽成默认比例（0–100），未在单场拍卖中指定时采用
+    type FeePercent: Get<u32>;
+    /// 拍卖行/国库收款账户
+    type AuctionHouse: Get<Self::AccountId>;
 }
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
+/// 每个区块 on_finalize 最多处理的拍卖条数，防止无界遍历拖慢出块
+const MAX_AUCTIONS_PER_BLOCK: usize = 100;
+
 #[derive(Encode, Decode, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub enum AuctionStatus {
     NotStarted, //未开卖
     Started, //正在拍卖（拍卖开始）
+    EndingPeriod, //处于蜡烛拍卖结束期（采样随机截止点）
     Paused, //拍卖暂停
     Selled, // 拍卖成功
     Unselled, //流拍
@@ -27,6 +49,18 @@ impl Default for AuctionStatus {
     }
 }
 
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum AuctionKind {
+    English, //英式拍卖（升价）
+    Dutch, //荷兰式拍卖（降价）
+}
+impl Default for AuctionKind {
+    fn default() -> Self {
+        AuctionKind::English
+    }
+}
+
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct AuctionRecord<T> where T: Trait {
@@ -38,25 +72,43 @@ pub struct AuctionRecord<T> where T: Trait {
 
     start_price: BalanceOf<T>, // 起拍价
     current_price: BalanceOf<T>, // 当前价格
+    ending_price: BalanceOf<T>, // 荷兰式拍卖的终止价（英式拍卖忽略）
     bid_range: BalanceOf<T>, // 加价幅度
+    duration: u64, // 拍卖持续时长（秒），结束时间 = begin_time + duration；荷兰式拍卖亦以此为降价周期
 
+    auction_kind: AuctionKind, // 拍卖方式（英式/荷兰式）
+    candle: bool, // 是否启用蜡烛拍卖（随机截止）机制
+    owner_cut: u32, // 拍卖行抽成比例（0–100）
     status: AuctionStatus, // 拍卖品状态
     item_receiver: Option<<T as system::Trait>::AccountId>, //拍卖成功后，拍品的接收方
     item_seller: <T as system::Trait>::AccountId, //拍卖品收款方
 }
 
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Bidder<T> where T: Trait {
+    account: <T as system::Trait>::AccountId, //竞拍者
+    amount: BalanceOf<T>, //出价金额
+    bid_at: u64, //出价时间(时间戳)
+}
+
 decl_storage! {
 	trait Store for Module<T: Trait> as Auctions {
         pub AuctionRecords get(record): map [u8;16] => Option<AuctionRecord<T>>; //存储 record_id => record
         pub RecordIds get(record_id): map (T::AccountId, [u8;16]) => [u8;16]; // 存储 (user, item_id) => (record_id)
         pub AuctionsItemRecord get(auction_item_record): map [u8; 16] => T::AccountId; // 存储 item_id => user
+        pub BidHistory get(bid_history): map [u8; 16] => Vec<Bidder<T>>; // 存储 record_id => 竞拍出价历史
+        pub ActiveAuctions get(active_auctions): Vec<[u8; 16]>; // 进行中的拍卖索引，供定时结算遍历，避免扫描全部记录
+        pub Winning get(winning): map ([u8; 16], u64) => Option<(T::AccountId, BalanceOf<T>)>; // 存储 (record_id, 子区块偏移) => 该子区块的领先出价
 	}
 }
 
 decl_event!(
 	pub enum Event<T>
-    where <T as system::Trait>::AccountId, <T as system::Trait>::Hash {
+    where <T as system::Trait>::AccountId, <T as system::Trait>::Hash, Balance = BalanceOf<T> {
         Created(AccountId, Hash),
+        BidPlaced(AccountId, [u8; 16], Balance), //有人出价：竞拍者、拍卖记录ID、出价金额
+        SettlementPaid(Balance, Balance), //成交结算：收款方所得金额、拍卖行抽成金额
     }
 );
 
@@ -64,13 +116,35 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
+		/// 区块终结时驱动定时结算：单次遍历进行中的拍卖索引（每块有上限），记录蜡烛采样并将已过结束时间者自动转为成交/流拍
+		fn on_finalize(_n: T::BlockNumber) {
+			let now = Self::get_current_time();
+			Self::process_active_auctions(now);
+		}
+
+		// 定时结算完全由 on_finalize 落链完成（见上），无需链下工作者；故此处不实现 offchain_worker
+
 		/// 创建拍卖物品纪录（上市），各参数含义参照struct
-		pub fn create_auction(origin, item_id: [u8; 16], begin_time: u64, start_price: BalanceOf<T>, bid_range: BalanceOf<T>, item_seller: T::AccountId) {
+		pub fn create_auction(origin, item_id: [u8; 16], begin_time: u64, start_price: BalanceOf<T>, ending_price: BalanceOf<T>, bid_range: BalanceOf<T>, duration: u64, auction_kind: AuctionKind, candle: bool, owner_cut: Option<u32>, item_seller: T::AccountId) {
 			let sender = ensure_signed(origin)?;
 
             // 参数检查
             ensure!((bid_range > <BalanceOf<T>>::from(0)), "加价幅度不可为0");
-			
+            // 结束时间由 begin_time + duration 推算，故持续时长不可为0
+            ensure!(duration > 0, "拍卖持续时长不可为0");
+
+            // 未指定抽成比例时采用拍卖行默认比例，且须落在 0–100 区间
+            let owner_cut = owner_cut.unwrap_or(T::FeePercent::get());
+            ensure!(owner_cut <= 100, "抽成比例须介于0至100之间");
+
+            // 荷兰式拍卖须降价
+            if auction_kind == AuctionKind::Dutch {
+                ensure!(ending_price < start_price, "荷兰式拍卖终止价须低于起拍价");
+            }
+
+            // 拒绝上架收款方（结算时的转出方）并不拥有的拍品
+            ensure!(T::ItemTransfer::is_item_owner(&item_seller, item_id), "无权上架非收款方拥有的拍品");
+
             // 1、判断当前物品是否在拍卖状态
             ensure!(!<AuctionsItemRecord<T>>::exists(&item_id), "此物品已在拍卖状态");
 
@@ -81,10 +155,15 @@ decl_module! {
                 record_id,
                 item_id,
                 begin_time,
-                end_time: None,
+                end_time: Some(begin_time.saturating_add(duration)),
                 start_price,
                 current_price: <BalanceOf<T>>::from(0),
+                ending_price,
                 bid_range,
+                duration,
+                auction_kind,
+                candle,
+                owner_cut,
                 status: AuctionStatus::NotStarted,
                 item_receiver: None,
                 item_seller: item_seller.clone(),
@@ -94,41 +173,101 @@ decl_module! {
             <AuctionRecords<T>>::insert(record_id, new_auction);
             <RecordIds<T>>::insert((item_seller.clone(), item_id), record_id);
             <AuctionsItemRecord<T>>::insert(item_id, item_seller);
+            <ActiveAuctions<T>>::mutate(|list| list.push(record_id));
 		}
 
-        /// 创建竞拍纪录
-		pub fn create_auction_record(origin, auction_user: T::AccountId, record_id: [u8; 16]) {
+        /// 出价竞拍：锁定出价人资金，刷新当前价与领先者，并退还上一位领先者的锁定资金
+		pub fn bid(origin, record_id: [u8; 16], amount: BalanceOf<T>) {
 			let sender = ensure_signed(origin)?;
-			
-			// 1、判断是否创建拍卖的人进行竞拍
-			ensure!(sender == auction_user, "竞拍者不能为发布拍品人");
-			// 2、判断拍品是否存在
+
+			// 1、判断拍品是否存在
 			ensure!(<AuctionRecords<T>>::exists(&record_id), "不存在此拍卖");
-			// 3、判断拍品状态
-			let auction_record = Self::record(record_id).unwrap();
-			ensure!(auction_record.status == AuctionStatus::NotStarted, "此拍卖品当前不可拍卖");
+			let mut auction_record = Self::record(record_id).unwrap();
+
+			// 2、仅英式拍卖支持出价竞拍（荷兰式拍卖仅可立即购买）
+			ensure!(auction_record.auction_kind == AuctionKind::English, "荷兰式拍卖请使用立即购买");
+			// 发布拍品人不可自拍
+			ensure!(sender != auction_record.item_seller, "竞拍者不能为发布拍品人");
+			// 3、仅未开卖、拍卖中或结束期可出价
+			ensure!(auction_record.status == AuctionStatus::NotStarted || auction_record.status == AuctionStatus::Started || auction_record.status == AuctionStatus::EndingPeriod, "此拍卖品当前不可拍卖");
 
-			let now: u64 = Self::get_current_time();
+			// 4、校验出价时间窗口：须在开始之后、结束之前
+			let now = Self::get_current_time();
 			ensure!(now >= auction_record.begin_time, "拍卖尚未开始");
+			ensure!(auction_record.end_time.map_or(true, |end_time| now <= end_time), "拍卖已结束");
 
-			// 已超时不可拍卖
-			if (!auction_record.end_time.is_some()) || (now > auction_record.end_time.unwrap()) {
-                if auction_record.item_receiver.is_some() {
-                    Self::change_auction_status(&sender, record_id, AuctionStatus::Selled)?;// 此处可不进行操作，正常应有定时操作进行时间方面的检查
-                } else {
-                    // 流拍，没有人购买
-                    Self::change_auction_status(&sender, record_id, AuctionStatus::Unselled)?;
-                }
-			} else if auction_record.status == AuctionStatus::Started {
-				// 未超时，且可进行拍卖
-                //TODO 此时需要进行何种操作？auction_record中存在current_price，是否要修改？
-                //auction_record
-
-				// let current_price = Self::auction_price(record_id);
-				// <AuctionsRecord<T>>::insert((record_id, auction_user), current_price + 1);
-				// ActionPrice::insert(record_id, current_price + 1);
+			// 5、校验出价：首次出价须不低于起拍价，其后须不低于当前价加上加价幅度
+			if auction_record.item_receiver.is_none() {
+				ensure!(amount >= auction_record.start_price, "出价不可低于起拍价");
+			} else {
+				ensure!(amount >= auction_record.current_price + auction_record.bid_range, "出价须不低于当前价加上加价幅度");
 			}
 
+			// 6、锁定出价人资金
+			T::Currency::reserve(&sender, amount).map_err(|_| "余额不足，无法锁定竞拍资金")?;
+
+			// 7、退还上一位领先竞拍者锁定的资金
+			//    蜡烛拍卖的中标者由随机采样决定，故结束期前即保留全部出价人的锁定资金，结算时统一退还
+			if !Self::is_candle(&auction_record) {
+				if let Some(prev) = auction_record.item_receiver.clone() {
+					T::Currency::unreserve(&prev, auction_record.current_price);
+				}
+			}
+
+			// 8、记录本次出价
+			<BidHistory<T>>::mutate(record_id, |history| history.push(Bidder::<T> {
+				account: sender.clone(),
+				amount,
+				bid_at: now,
+			}));
+
+			// 9、刷新当前价与领先者（结束期状态保持不变，仅将未开卖置为拍卖中）
+			auction_record.current_price = amount;
+			auction_record.item_receiver = Some(sender.clone());
+			if auction_record.status == AuctionStatus::NotStarted {
+				auction_record.status = AuctionStatus::Started;
+			}
+			<AuctionRecords<T>>::insert(record_id, auction_record);
+
+			Self::deposit_event(RawEvent::BidPlaced(sender, record_id, amount));
+		}
+
+        /// 荷兰式拍卖立即购买：首位调用者按当前降价价格向收款方付款并直接成交，避免无谓的落败出价
+		pub fn buy_now(origin, record_id: [u8; 16]) {
+			let sender = ensure_signed(origin)?;
+
+			// 1、判断拍品是否存在
+			ensure!(<AuctionRecords<T>>::exists(&record_id), "不存在此拍卖");
+			let mut auction_record = Self::record(record_id).unwrap();
+
+			// 2、仅荷兰式拍卖支持立即购买
+			ensure!(auction_record.auction_kind == AuctionKind::Dutch, "此拍卖非荷兰式拍卖");
+			// 3、发布拍品人不可自购
+			ensure!(sender != auction_record.item_seller, "竞拍者不能为发布拍品人");
+			// 4、仅未开卖或拍卖中可购买
+			ensure!(auction_record.status == AuctionStatus::NotStarted || auction_record.status == AuctionStatus::Started, "此拍卖品当前不可拍卖");
+
+			let now = Self::get_current_time();
+			ensure!(now >= auction_record.begin_time, "拍卖尚未开始");
+
+			// 5、先转移拍品所有权，再按当前降价价格付款；付款放在最后以避免转移失败后买家已扣款却无拍品
+			let price = Self::current_dutch_price(record_id).ok_or("无法计算当前荷兰式拍卖价格")?;
+			let (seller_amount, fee_amount) = Self::split_fee(&auction_record, price);
+			T::ItemTransfer::transfer_item(&auction_record.item_seller, &sender, auction_record.item_id)?;
+			if fee_amount > <BalanceOf<T>>::from(0) {
+				T::Currency::transfer(&sender, &T::AuctionHouse::get(), fee_amount)?;
+			}
+			T::Currency::transfer(&sender, &auction_record.item_seller, seller_amount)?;
+			Self::deposit_event(RawEvent::SettlementPaid(seller_amount, fee_amount));
+
+			// 6、直接成交
+			auction_record.current_price = price;
+			auction_record.item_receiver = Some(sender.clone());
+			auction_record.status = AuctionStatus::Selled;
+			<AuctionRecords<T>>::insert(record_id, auction_record);
+			<ActiveAuctions<T>>::mutate(|list| list.retain(|id| *id != record_id));
+
+			Self::deposit_event(RawEvent::BidPlaced(sender, record_id, price));
 		}
 
 		/// 结算(应该为定时任务判断时间主动结束，此处采用用户手动结束方式)
@@ -153,14 +292,14 @@ decl_module! {
 			if (auction_record.status == AuctionStatus::Selled) || (auction_record.status == AuctionStatus::Unselled) {
 				//  若为已经完成拍卖，则结束不进行任何操作
 			} else if auction_record.end_time.is_some() && (auction_record.end_time.unwrap() < now) {
-				// 此条件中应放入定时任务
-				if auction_record.current_price == auction_record.start_price {
-					Self::change_auction_status(&sender, item_id, AuctionStatus::Unselled)?;
+				// 已过结束时间但尚未由 on_finalize 结算：无人出价则流拍，否则成交
+				if auction_record.item_receiver.is_none() {
+					Self::change_auction_status(&sender, record_id, AuctionStatus::Unselled)?;
 				} else {
-					Self::change_auction_status(&sender, item_id, AuctionStatus::Selled)?; // 此处可不进行操作，正常应有定时操作进行时间方面的检查
+					Self::change_auction_status(&sender, record_id, AuctionStatus::Selled)?; // 此处可不进行操作，正常应有定时操作进行时间方面的检查
 				}
 			} else {
-				Self::change_auction_status(&sender, item_id, AuctionStatus::Selled)?;
+				Self::change_auction_status(&sender, record_id, AuctionStatus::Selled)?;
 			}
 		}
 	}
@@ -182,17 +321,449 @@ impl<T: Trait> Module<T> {
 
         let mut auction_record = Self::record(record_id).unwrap();
 		ensure!(auction_record.item_seller != *sender, "用户无此拍卖信息");
-		
-		// 2、修改拍卖信息
-		auction_record.status = status;
+
+		// 2、结算托管资金：成交则转移拍品并把领先者锁定资金按抽成拆分划转给拍卖行与收款方，流拍则退还领先者
+		Self::settle_escrow(&auction_record, &status)?;
+
+		// 3、修改拍卖信息
+		auction_record.status = status.clone();
 		<AuctionRecords<T>>::insert(record_id, auction_record);
 
+		// 4、完结的拍卖从进行中索引移除
+		if status == AuctionStatus::Selled || status == AuctionStatus::Unselled {
+			<ActiveAuctions<T>>::mutate(|list| list.retain(|id| *id != record_id));
+		}
+
 		Ok(())
 	}
 
-    /// 获取当前时间
+    /// 按抽成比例拆分成交金额，返回 (收款方所得, 抽成金额)；用饱和运算并保证两部分之和不超过成交额
+    fn split_fee(record: &AuctionRecord<T>, total: BalanceOf<T>) -> (BalanceOf<T>, BalanceOf<T>) {
+        let cut = if record.owner_cut > 100 { 100 } else { record.owner_cut };
+        let fee = total.saturating_mul(<BalanceOf<T> as As<u64>>::sa(cut as u64)) / <BalanceOf<T> as As<u64>>::sa(100);
+        let seller_amount = total.saturating_sub(fee);
+        (seller_amount, fee)
+    }
+
+    /// 从中标者的锁定资金中按抽成拆分，分别划转给拍卖行与收款方，并发出结算事件
+    fn payout_reserved_with_fee(winner: &T::AccountId, record: &AuctionRecord<T>, total: BalanceOf<T>) -> result::Result<(), &'static str> {
+        let (seller_amount, fee_amount) = Self::split_fee(record, total);
+        if fee_amount > <BalanceOf<T>>::from(0) {
+            let _ = T::Currency::repatriate_reserved(winner, &T::AuctionHouse::get(), fee_amount)?;
+        }
+        let _ = T::Currency::repatriate_reserved(winner, &record.item_seller, seller_amount)?;
+        Self::deposit_event(RawEvent::SettlementPaid(seller_amount, fee_amount));
+        Ok(())
+    }
+
+    /// 结算托管资金：成交则转移拍品并把领先者锁定资金按抽成拆分划转给拍卖行与收款方，流拍则退还领先者
+    fn settle_escrow(record: &AuctionRecord<T>, status: &AuctionStatus) -> result::Result<(), &'static str> {
+        match status {
+            AuctionStatus::Selled => {
+                if let Some(winner) = record.item_receiver.clone() {
+                    // 先转移拍品所有权，再划转资金（含抽成）；资金划转放在最后一步以避免失败后重复划账
+                    T::ItemTransfer::transfer_item(&record.item_seller, &winner, record.item_id)?;
+                    Self::payout_reserved_with_fee(&winner, record, record.current_price)?;
+                }
+            },
+            AuctionStatus::Unselled => {
+                if let Some(winner) = record.item_receiver.clone() {
+                    T::Currency::unreserve(&winner, record.current_price);
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// 单次遍历进行中的拍卖索引：记录蜡烛子区块采样，并结算已过结束时间者；每块处理量有上限
+    fn process_active_auctions(now: u64) {
+        let active = Self::active_auctions();
+        let mut remaining = Vec::new();
+        let mut processed = 0usize;
+        for record_id in active {
+            // 超过每块处理上限的拍卖顺延至后续区块
+            if processed >= MAX_AUCTIONS_PER_BLOCK {
+                remaining.push(record_id);
+                continue;
+            }
+            let mut auction_record = match Self::record(record_id) {
+                Some(r) => r,
+                None => continue, // 记录已不存在，从索引剔除
+            };
+            processed += 1;
+
+            let expired = auction_record.end_time.map_or(false, |end_time| end_time < now);
+            let pending = auction_record.status == AuctionStatus::NotStarted || auction_record.status == AuctionStatus::Started || auction_record.status == AuctionStatus::EndingPeriod;
+
+            if expired && pending {
+                // 蜡烛拍卖按随机采样点选出中标者，其余按常规（末位最高价）结算
+                let ok = if Self::is_candle(&auction_record) {
+                    Self::settle_candle(&mut auction_record)
+                } else {
+                    // 有领先出价者则成交，否则流拍
+                    let status = if auction_record.item_receiver.is_some() {
+                        AuctionStatus::Selled
+                    } else {
+                        AuctionStatus::Unselled
+                    };
+                    Self::settle_escrow(&auction_record, &status).map(|_| auction_record.status = status)
+                };
+                if ok.is_ok() {
+                    <AuctionRecords<T>>::insert(record_id, auction_record);
+                } else {
+                    remaining.push(record_id);
+                }
+                continue;
+            }
+
+            // 未到期：蜡烛拍卖进入结束期时记录本子区块的领先出价
+            if pending && Self::is_candle(&auction_record) {
+                if let Some(offset) = Self::ending_period_offset(&auction_record, now) {
+                    let winning = auction_record.item_receiver.clone().map(|account| (account, auction_record.current_price));
+                    <Winning<T>>::insert((record_id, offset), winning);
+                    if auction_record.status != AuctionStatus::EndingPeriod {
+                        auction_record.status = AuctionStatus::EndingPeriod;
+                        <AuctionRecords<T>>::insert(record_id, auction_record);
+                    }
+                }
+            }
+            remaining.push(record_id);
+        }
+        <ActiveAuctions<T>>::put(remaining);
+    }
+
+    /// 计算荷兰式拍卖的当前线性插值价格：超过降价周期则取终止价，否则按已过时长线性下降
+    pub fn current_dutch_price(record_id: [u8; 16]) -> Option<BalanceOf<T>> {
+        let record = Self::record(record_id)?;
+        if record.auction_kind != AuctionKind::Dutch {
+            return None;
+        }
+
+        let now = Self::get_current_time();
+        let elapsed = now.saturating_sub(record.begin_time);
+        if elapsed >= record.duration {
+            return Some(record.ending_price);
+        }
+
+        // price = start_price - (start_price - ending_price) * elapsed / duration
+        let spread = record.start_price.checked_sub(&record.ending_price)?;
+        let drop = spread
+            .checked_mul(&<BalanceOf<T> as As<u64>>::sa(elapsed))?
+            .checked_div(&<BalanceOf<T> as As<u64>>::sa(record.duration))?;
+        // 下降幅度不应超过差价，兜底取终止价
+        let price = record.start_price.checked_sub(&drop).unwrap_or(record.ending_price);
+        Some(price)
+    }
+
+    /// 是否为启用了蜡烛机制的英式拍卖（须单场拍卖显式开启，且已在配置中设置结束期与采样数）
+    fn is_candle(record: &AuctionRecord<T>) -> bool {
+        record.candle && record.auction_kind == AuctionKind::English && T::EndingPeriod::get() > 0 && T::SampleCount::get() > 0
+    }
+
+    /// 若当前处于结束期，返回所处的子区块偏移；否则返回 None
+    fn ending_period_offset(record: &AuctionRecord<T>, now: u64) -> Option<u64> {
+        let end_time = record.end_time?;
+        let ending = T::EndingPeriod::get();
+        let count = T::SampleCount::get();
+        if ending == 0 || count == 0 {
+            return None;
+        }
+        let start = end_time.saturating_sub(ending);
+        if now < start || now >= end_time {
+            return None;
+        }
+        let offset = now.saturating_sub(start).saturating_mul(count) / ending;
+        Some(if offset >= count { count - 1 } else { offset })
+    }
+
+    /// 以 record_id 混合 random_seed 抽取结束期内的随机采样偏移
+    fn candle_sample_offset(record_id: [u8; 16]) -> u64 {
+        let count = T::SampleCount::get();
+        if count == 0 {
+            return 0;
+        }
+        let hash = (<system::Module<T>>::random_seed(), record_id).using_encoded(blake2_128);
+        let mut raw = [0u8; 8];
+        raw.copy_from_slice(&hash[0..8]);
+        u64::from_le_bytes(raw) % count
+    }
+
+    /// 取随机采样偏移处的领先出价；该子区块无记录时向前回溯，实现“无新出价则沿用上一子区块”的结转
+    fn candle_winner(record_id: [u8; 16]) -> Option<(T::AccountId, BalanceOf<T>)> {
+        let offset = Self::candle_sample_offset(record_id);
+        let mut i = offset as i64;
+        while i >= 0 {
+            if let Some(winning) = Self::winning((record_id, i as u64)) {
+                return Some(winning);
+            }
+            i -= 1;
+        }
+        None
+    }
+
+    /// 结算蜡烛拍卖：随机采样确定中标者，将其中标价划转给收款方并转移拍品，其余出价人全额退还锁定资金
+    fn settle_candle(record: &mut AuctionRecord<T>) -> result::Result<(), &'static str> {
+        // 汇总每位出价人累计锁定的资金
+        let mut totals: Vec<(T::AccountId, BalanceOf<T>)> = Vec::new();
+        for bidder in Self::bid_history(record.record_id).iter() {
+            if let Some(entry) = totals.iter_mut().find(|(account, _)| *account == bidder.account) {
+                entry.1 = entry.1 + bidder.amount;
+            } else {
+                totals.push((bidder.account.clone(), bidder.amount));
+            }
+        }
+
+        let winner = if record.item_receiver.is_some() {
+            Self::candle_winner(record.record_id)
+        } else {
+            None
+        };
+
+        match winner {
+            Some((winner_account, winning_amount)) => {
+                for (account, total) in totals.iter() {
+                    if *account == winner_account {
+                        // 中标者：先转移拍品，再按抽成拆分划转中标价并退还多余锁定
+                        T::ItemTransfer::transfer_item(&record.item_seller, account, record.item_id)?;
+                        Self::payout_reserved_with_fee(account, record, winning_amount)?;
+                        if *total > winning_amount {
+                            T::Currency::unreserve(account, *total - winning_amount);
+                        }
+                    } else {
+                        T::Currency::unreserve(account, *total);
+                    }
+                }
+                record.item_receiver = Some(winner_account);
+                record.current_price = winning_amount;
+                record.status = AuctionStatus::Selled;
+            },
+            None => {
+                for (account, total) in totals.iter() {
+                    T::Currency::unreserve(account, *total);
+                }
+                record.status = AuctionStatus::Unselled;
+            },
+        }
+        Ok(())
+    }
+
+    /// 获取当前时间(取自 timestamp 模块的当前区块时间戳)
     pub fn get_current_time() -> u64 {
-        // TODO: 获取当前时间
-        0
+        <timestamp::Module<T>>::get().as_()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use primitives::{H256, Blake2Hasher};
+    use support::{impl_outer_origin, parameter_types, assert_ok, assert_noop, traits::Currency};
+    use sr_primitives::{
+        BuildStorage,
+        traits::{BlakeTwo256, IdentityLookup},
+        testing::Header,
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = sr_primitives::testing::Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = sr_primitives::testing::DigestItem;
+    }
+
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type TransferPayment = ();
+        type DustRemoval = ();
+    }
+
+    parameter_types! {
+        pub const MinimumPeriod: u64 = 1;
+    }
+    impl timestamp::Trait for Test {
+        type Moment = u64;
+        type OnTimestampSet = ();
+    }
+
+    // 测试用拍品归属桩：默认所有人皆为拥有者，转移恒成功
+    pub struct ItemTransferStub;
+    impl ItemTransfer<u64, [u8; 16]> for ItemTransferStub {
+        fn is_item_owner(_who: &u64, _item: [u8; 16]) -> bool { true }
+        fn transfer_item(_from: &u64, _to: &u64, _item: [u8; 16]) -> result::Result<(), &'static str> { Ok(()) }
+    }
+
+    parameter_types! {
+        pub const EndingPeriod: u64 = 10;
+        pub const SampleCount: u64 = 5;
+        pub const FeePercent: u32 = 10;
+        pub const AuctionHouse: u64 = 99;
+    }
+    impl Trait for Test {
+        type Event = ();
+        type Currency = balances::Module<Test>;
+        type ItemTransfer = ItemTransferStub;
+        type AuctionIndex = u64;
+        type EndingPeriod = EndingPeriod;
+        type SampleCount = SampleCount;
+        type FeePercent = FeePercent;
+        type AuctionHouse = AuctionHouse;
+    }
+
+    type Auction = Module<Test>;
+    type Balances = balances::Module<Test>;
+    type Timestamp = timestamp::Module<Test>;
+
+    const SELLER: u64 = 1;
+    const ALICE: u64 = 2;
+    const BOB: u64 = 3;
+    const ITEM: [u8; 16] = [7u8; 16];
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+        t.extend(balances::GenesisConfig::<Test> {
+            balances: vec![(SELLER, 1_000), (ALICE, 1_000), (BOB, 1_000)],
+            vesting: vec![],
+            transaction_base_fee: 0,
+            transaction_byte_fee: 0,
+            existential_deposit: 0,
+            transfer_fee: 0,
+            creation_fee: 0,
+        }.build_storage().unwrap().0);
+        t.into()
+    }
+
+    // 取出当前唯一一场拍卖的 record_id
+    fn only_record_id() -> [u8; 16] {
+        let ids = Auction::active_auctions();
+        assert_eq!(ids.len(), 1);
+        ids[0]
+    }
+
+    fn create_english(candle: bool) {
+        assert_ok!(Auction::create_auction(
+            Origin::signed(SELLER), ITEM, 0, 100, 0, 10, 100,
+            AuctionKind::English, candle, None, SELLER,
+        ));
+    }
+
+    #[test]
+    fn first_bid_must_reach_start_price_then_raise_by_range() {
+        with_externalities(&mut new_test_ext(), || {
+            Timestamp::set_timestamp(1);
+            create_english(false);
+            let record_id = only_record_id();
+
+            // 首次出价低于起拍价应被拒
+            assert_noop!(Auction::bid(Origin::signed(ALICE), record_id, 90), "出价不可低于起拍价");
+            // 达到起拍价则成功
+            assert_ok!(Auction::bid(Origin::signed(ALICE), record_id, 100));
+            assert_eq!(Auction::record(record_id).unwrap().current_price, 100);
+
+            // 加价不足（未达当前价 + 加价幅度）应被拒
+            assert_noop!(Auction::bid(Origin::signed(BOB), record_id, 105), "出价须不低于当前价加上加价幅度");
+            // 满足加价幅度则成功
+            assert_ok!(Auction::bid(Origin::signed(BOB), record_id, 110));
+            assert_eq!(Auction::record(record_id).unwrap().item_receiver, Some(BOB));
+        });
+    }
+
+    #[test]
+    fn outbid_refunds_previous_leader() {
+        with_externalities(&mut new_test_ext(), || {
+            Timestamp::set_timestamp(1);
+            create_english(false);
+            let record_id = only_record_id();
+
+            assert_ok!(Auction::bid(Origin::signed(ALICE), record_id, 100));
+            assert_eq!(Balances::reserved_balance(&ALICE), 100);
+
+            // 被超越后，前一位领先者的锁定资金应被退还
+            assert_ok!(Auction::bid(Origin::signed(BOB), record_id, 110));
+            assert_eq!(Balances::reserved_balance(&ALICE), 0);
+            assert_eq!(Balances::reserved_balance(&BOB), 110);
+        });
+    }
+
+    #[test]
+    fn fee_split_sums_exactly_to_escrowed_total() {
+        with_externalities(&mut new_test_ext(), || {
+            create_english(false);
+            let record_id = only_record_id();
+            let record = Auction::record(record_id).unwrap();
+            // owner_cut 已按默认 FeePercent = 10% 设定
+            assert_eq!(record.owner_cut, 10);
+            let (seller_amount, fee_amount) = Auction::split_fee(&record, 137);
+            assert_eq!(fee_amount, 13); // floor(137 * 10 / 100)
+            assert_eq!(seller_amount, 124);
+            assert_eq!(seller_amount + fee_amount, 137); // 两部分之和不超过托管总额
+        });
+    }
+
+    #[test]
+    fn dutch_price_interpolates_linearly() {
+        with_externalities(&mut new_test_ext(), || {
+            Timestamp::set_timestamp(0);
+            assert_ok!(Auction::create_auction(
+                Origin::signed(SELLER), ITEM, 0, 100, 20, 10, 10,
+                AuctionKind::Dutch, false, Some(0), SELLER,
+            ));
+            let record_id = only_record_id();
+
+            // t = 0 时取起拍价
+            assert_eq!(Auction::current_dutch_price(record_id), Some(100));
+            // t = duration/2 时取中间价：100 - (100-20)*5/10 = 60
+            Timestamp::set_timestamp(5);
+            assert_eq!(Auction::current_dutch_price(record_id), Some(60));
+            // 超过周期取终止价
+            Timestamp::set_timestamp(20);
+            assert_eq!(Auction::current_dutch_price(record_id), Some(20));
+        });
+    }
+
+    #[test]
+    fn candle_carries_winning_forward_through_empty_sub_blocks() {
+        with_externalities(&mut new_test_ext(), || {
+            Timestamp::set_timestamp(0);
+            create_english(true); // 结束时间 = 0 + 100 = 100，结束期为最后 10 秒
+            let record_id = only_record_id();
+
+            // 结束期之前出价
+            Timestamp::set_timestamp(80);
+            assert_ok!(Auction::bid(Origin::signed(ALICE), record_id, 100));
+
+            // 进入结束期且此后无人出价，逐子区块推进 on_finalize
+            for moment in 90..100 {
+                Timestamp::set_timestamp(moment);
+                <Auction as support::traits::OnFinalize<u64>>::on_finalize(moment);
+            }
+            assert_eq!(Auction::record(record_id).unwrap().status, AuctionStatus::EndingPeriod);
+
+            // 结束后结算：无论随机采样落在哪个子区块，领先出价都被结转，胜者仍为 ALICE
+            Timestamp::set_timestamp(101);
+            <Auction as support::traits::OnFinalize<u64>>::on_finalize(101);
+            let settled = Auction::record(record_id).unwrap();
+            assert_eq!(settled.status, AuctionStatus::Selled);
+            assert_eq!(settled.item_receiver, Some(ALICE));
+        });
     }
 }
\ No newline at end of file